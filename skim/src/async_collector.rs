@@ -0,0 +1,146 @@
+//! Bridges an async byte stream into skim's existing [`SkimItemReceiver`]/interrupt pipeline,
+//! for applications that already run on an async runtime and would rather not have skim spawn
+//! its own OS reader thread.
+//!
+//! Gated behind the `async` feature (pulls in `futures` for [`AsyncRead`], the `select`
+//! combinator, and the task-waking primitives used by [`InterruptSignal`]).
+//!
+//! Unlike [`crate::reader::CommandCollector`], which is invoked by `Reader::run` and owns the
+//! whole "start a producer" step, this is meant to be driven by the caller and fed straight
+//! into [`crate::reader::Reader::source`]: build the bridge, spawn the returned future on your
+//! own executor (`tokio::spawn`, `async_std::task::spawn`, ...), and pass `(rx_item,
+//! tx_interrupt)` to `Reader::source`, which forwards `kill()`'s interrupt into `tx_interrupt`
+//! the same way it does for a `CommandCollector`. The bridge itself spawns no OS thread (only
+//! `Reader::run`'s usual `collect_item` thread, which drains `rx_item`, still runs):
+//! `drive` races the next line against [`InterruptSignal`], which registers the polling task's
+//! waker and is woken directly from `kill()` -- no parked thread, no polling loop.
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
+
+use crossbeam::channel::{TrySendError, bounded};
+use futures::future::{Either, select};
+use futures::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use futures::stream::StreamExt;
+use futures::task::AtomicWaker;
+
+use crate::reader::SourceInterrupt;
+use crate::{SkimItem, SkimItemReceiver};
+
+const CHANNEL_SIZE: usize = 1024;
+
+/// yield back to the executor once, so a full channel lets other tasks run between retries
+/// instead of holding the worker thread; the task wakes itself at once, so it keeps retrying
+/// until `collect_item`'s thread drains the channel
+async fn yield_now() {
+    let mut yielded = false;
+    futures::future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+    .await
+}
+
+/// Shared state behind [`InterruptSignal`]: a fired flag plus the [`AtomicWaker`] needed to
+/// wake whichever task is currently awaiting it, so `interrupt()` (called synchronously from
+/// `ReaderControl::kill()`) can hand off to the polling task without a channel or a thread.
+#[derive(Default)]
+struct Inner {
+    fired: AtomicBool,
+    waker: AtomicWaker,
+}
+
+/// The async-aware counterpart of a `Sender<i32>` for [`crate::reader::Reader::source`]:
+/// `interrupt()` is a plain synchronous call (so `ReaderControl::kill()` can call it directly),
+/// but it wakes the task awaiting the matching future instead of requiring a thread parked on a
+/// blocking `recv()`.
+#[derive(Clone, Default)]
+pub struct InterruptSignal(Arc<Inner>);
+
+impl InterruptSignal {
+    fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SourceInterrupt for InterruptSignal {
+    fn interrupt(&self) {
+        self.0.fired.store(true, Ordering::Release);
+        self.0.waker.wake();
+    }
+}
+
+impl Future for InterruptSignal {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        // register before the check, not after, so an `interrupt()` landing between the check
+        // and the registration can't be missed
+        self.0.waker.register(cx.waker());
+        if self.0.fired.load(Ordering::Acquire) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Split `source` into lines, map each through `to_item`, and bridge them into a fresh
+/// `SkimItemReceiver`/interrupt pair compatible with `Reader::source`.
+///
+/// Returns `(rx_item, tx_interrupt, drive)`: pass `(rx_item, Box::new(tx_interrupt))` straight
+/// to `Reader::source` so `ReaderControl::kill()` can reach this bridge, and spawn `drive` on
+/// your own async runtime -- it resolves once `source` is exhausted or `tx_interrupt` is
+/// interrupted (mirroring `ReaderControl::kill`'s "any message stops it" contract).
+pub fn bridge<R>(
+    source: R,
+    to_item: impl Fn(String) -> Arc<dyn SkimItem> + Send + 'static,
+) -> (SkimItemReceiver, InterruptSignal, impl Future<Output = ()>)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    let (tx_item, rx_item) = bounded(CHANNEL_SIZE);
+    let tx_interrupt = InterruptSignal::new();
+    let interrupt = tx_interrupt.clone();
+
+    let drive = async move {
+        let mut lines = BufReader::new(source).lines();
+        loop {
+            // race the next line against the interrupt signal inside the same driven future,
+            // instead of parking a dedicated thread on an interrupt channel to abort this loop
+            // from outside -- this future never spawns an OS thread.
+            let line = match select(Box::pin(lines.next()), Box::pin(interrupt.clone())).await {
+                Either::Left((Some(line), _)) => line,
+                Either::Left((None, _)) => break, // source exhausted
+                Either::Right(_) => break,        // interrupted by `tx_interrupt`
+            };
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            // `try_send` + yield instead of the blocking `send`: this future is driven by the
+            // caller's executor, so a blocking call here would stall that executor's worker
+            // thread (and every other task on it, on a current-thread runtime) until
+            // `collect_item`'s thread drains the channel, not just park this task.
+            let mut item = to_item(line);
+            loop {
+                match tx_item.try_send(item) {
+                    Ok(()) => break,
+                    Err(TrySendError::Full(it)) => {
+                        item = it;
+                        yield_now().await;
+                    }
+                    Err(TrySendError::Disconnected(_)) => return,
+                }
+            }
+        }
+    };
+
+    (rx_item, tx_interrupt, drive)
+}
@@ -0,0 +1,333 @@
+//! A lock-free, append-only vector used by [`crate::item::ItemPool`] so that the reader
+//! thread can keep publishing items while the matcher concurrently snapshots them, without
+//! the two serializing on a shared lock.
+//!
+//! The layout follows `boxcar`/nucleo: bucket `n` holds `2^n` slots, so capacity grows
+//! geometrically and a published element never moves, which is what lets readers keep plain
+//! references into the structure while a writer keeps appending.
+
+use std::mem::MaybeUninit;
+
+use crate::sync::{AtomicBool, AtomicPtr, AtomicUsize, Ordering, UnsafeCell};
+
+const NUM_BUCKETS: usize = usize::BITS as usize;
+
+/// `(bucket, offset)` for a given linear index, derived from the bit-length of `index + 1`.
+fn locate(index: usize) -> (usize, usize) {
+    let i = index + 1;
+    let bucket = (usize::BITS - i.leading_zeros() - 1) as usize;
+    let offset = i - (1 << bucket);
+    (bucket, offset)
+}
+
+fn bucket_len(bucket: usize) -> usize {
+    1 << bucket
+}
+
+struct Slot<T> {
+    published: AtomicBool,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> Slot<T> {
+    fn empty() -> Self {
+        Self {
+            published: AtomicBool::new(false),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+struct Bucket<T> {
+    slots: Box<[Slot<T>]>,
+}
+
+/// A concurrent, append-only collection: many readers may iterate while a writer appends,
+/// with no lock on the hot path.
+pub struct Boxcar<T> {
+    len: AtomicUsize,
+    /// number of slots whose `extend()` batch has fully published, i.e. the prefix `0..committed`
+    /// has no holes; only ever advances by a whole batch at a time, in reservation order
+    committed: AtomicUsize,
+    buckets: [AtomicPtr<Bucket<T>>; NUM_BUCKETS],
+    /// number of live [`ReadGuard`]s, i.e. readers that called [`Boxcar::enter`] and may hold
+    /// `get`/`iter` borrows into the buckets (plain `get`/`iter` calls are not counted);
+    /// `clear()` spins until this reaches zero before freeing anything, so it can
+    /// never free a bucket a reader is still dereferencing
+    readers: AtomicUsize,
+    /// number of `extend()` calls currently writing into a bucket; `clear()` spins until this
+    /// reaches zero too, for the same reason as `readers` -- a bucket `extend()` is still
+    /// writing into must not be freed out from under it
+    writers: AtomicUsize,
+    /// set for the duration of a `clear()`; blocks new `enter()`/`extend()` calls from starting
+    /// (and new `clear()` calls from overlapping) so the wait for `readers`/`writers` to drain
+    /// can't be raced by a caller that shows up just after the wait finished
+    clearing: AtomicBool,
+}
+
+/// Marks one reader as "inside" the boxcar for as long as it's alive, so a concurrent `clear()`
+/// knows to wait rather than freeing buckets out from under it.
+pub struct ReadGuard<'a, T> {
+    boxcar: &'a Boxcar<T>,
+}
+
+impl<T> Drop for ReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.boxcar.readers.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// Marks one `extend()` call as "inside" the boxcar for as long as it's alive, for the same
+/// reason as [`ReadGuard`]: a concurrent `clear()` must not free a bucket this write is still
+/// publishing into.
+struct WriteGuard<'a, T> {
+    boxcar: &'a Boxcar<T>,
+}
+
+impl<T> Drop for WriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.boxcar.writers.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<T> Default for Boxcar<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Boxcar<T> {
+    pub fn new() -> Self {
+        Self {
+            len: AtomicUsize::new(0),
+            committed: AtomicUsize::new(0),
+            buckets: std::array::from_fn(|_| AtomicPtr::new(std::ptr::null_mut())),
+            readers: AtomicUsize::new(0),
+            writers: AtomicUsize::new(0),
+            clearing: AtomicBool::new(false),
+        }
+    }
+
+    /// register the caller as a reader until the returned guard is dropped; `clear()` won't
+    /// free any bucket while at least one guard is outstanding
+    pub fn enter(&self) -> ReadGuard<'_, T> {
+        loop {
+            while self.clearing.load(Ordering::Acquire) {
+                std::hint::spin_loop();
+            }
+            self.readers.fetch_add(1, Ordering::Acquire);
+            if !self.clearing.load(Ordering::Acquire) {
+                break;
+            }
+            // a `clear()` started right as we registered: back out and retry once it's done,
+            // otherwise it might already be past its own wait for `readers` to reach zero
+            self.readers.fetch_sub(1, Ordering::Release);
+        }
+        ReadGuard { boxcar: self }
+    }
+
+    /// register the caller as a writer until the returned guard is dropped; `clear()` won't
+    /// free any bucket while at least one guard is outstanding (see [`Self::enter`])
+    fn enter_write(&self) -> WriteGuard<'_, T> {
+        loop {
+            while self.clearing.load(Ordering::Acquire) {
+                std::hint::spin_loop();
+            }
+            self.writers.fetch_add(1, Ordering::Acquire);
+            if !self.clearing.load(Ordering::Acquire) {
+                break;
+            }
+            self.writers.fetch_sub(1, Ordering::Release);
+        }
+        WriteGuard { boxcar: self }
+    }
+
+    /// number of slots reserved so far (some of the highest indices may not be published yet)
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::SeqCst)
+    }
+
+    /// number of slots published so far, i.e. safe to `take()`: unlike [`Self::len`], this never
+    /// includes an index whose `extend()` call reserved it but hasn't finished publishing
+    pub fn committed_len(&self) -> usize {
+        self.committed.load(Ordering::Acquire)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn bucket(&self, bucket_idx: usize) -> &Bucket<T> {
+        let slot = &self.buckets[bucket_idx];
+        let mut ptr = slot.load(Ordering::Acquire);
+        if ptr.is_null() {
+            let fresh = Box::into_raw(Box::new(Bucket {
+                slots: (0..bucket_len(bucket_idx)).map(|_| Slot::empty()).collect(),
+            }));
+            match slot.compare_exchange(std::ptr::null_mut(), fresh, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => ptr = fresh,
+                Err(existing) => {
+                    // lost the race, drop our allocation and use the winner's
+                    unsafe { drop(Box::from_raw(fresh)) };
+                    ptr = existing;
+                }
+            }
+        }
+        unsafe { &*ptr }
+    }
+
+    /// append `items`, reserving their slots with a single `fetch_add`, and return the new
+    /// committed (published) length
+    pub fn extend(&self, items: Vec<T>) -> usize {
+        if items.is_empty() {
+            return self.committed_len();
+        }
+        let _write = self.enter_write();
+        let start = self.len.fetch_add(items.len(), Ordering::SeqCst);
+        let end = start + items.len();
+        for (offset, value) in items.into_iter().enumerate() {
+            let index = start + offset;
+            let (bucket_idx, slot_idx) = locate(index);
+            let bucket = self.bucket(bucket_idx);
+            bucket.slots[slot_idx]
+                .value
+                .with_mut(|ptr| unsafe { (*ptr).write(value) });
+            bucket.slots[slot_idx].published.store(true, Ordering::Release);
+        }
+        // advance `committed` only once every earlier-reserved batch has committed, so it never
+        // runs ahead of a still-publishing predecessor and exposes a hole
+        while self
+            .committed
+            .compare_exchange_weak(start, end, Ordering::Release, Ordering::Relaxed)
+            .is_err()
+        {
+            std::hint::spin_loop();
+        }
+        self.committed_len()
+    }
+
+    /// load the item at `index`, if it has been reserved and published
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len() {
+            return None;
+        }
+        let (bucket_idx, slot_idx) = locate(index);
+        let ptr = self.buckets[bucket_idx].load(Ordering::Acquire);
+        if ptr.is_null() {
+            return None;
+        }
+        let slot = unsafe { &(*ptr).slots[slot_idx] };
+        if !slot.published.load(Ordering::Acquire) {
+            return None;
+        }
+        Some(slot.value.with(|ptr| unsafe { (*ptr).assume_init_ref() }))
+    }
+
+    /// iterate the published items in `range`, skipping any index that was reserved but not
+    /// yet published (the writer is mid-append)
+    pub fn iter(&self, range: std::ops::Range<usize>) -> impl Iterator<Item = &T> {
+        range.filter_map(move |index| self.get(index))
+    }
+
+    /// drop every published element and reset to an empty, unallocated state.
+    ///
+    /// Safe to call concurrently with `extend`, and with `get`/`iter` made while holding a
+    /// [`ReadGuard`] from [`Self::enter`]; `get`/`iter` without a guard are not waited for.
+    /// Setting `clearing` first blocks any
+    /// new `enter()`/`extend()` from starting (and any other concurrent `clear()` from
+    /// overlapping this one), then waiting for `readers` and `writers` to both drain ensures
+    /// every `extend()` that was already writing into a bucket has finished publishing and every
+    /// `ReadGuard`-holding reader has stopped dereferencing one, before any bucket is freed.
+    pub fn clear(&self) {
+        while self
+            .clearing
+            .compare_exchange_weak(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            std::hint::spin_loop();
+        }
+        while self.readers.load(Ordering::Acquire) != 0 || self.writers.load(Ordering::Acquire) != 0 {
+            std::hint::spin_loop();
+        }
+        let len = self.len.swap(0, Ordering::SeqCst);
+        self.committed.store(0, Ordering::SeqCst);
+        for index in 0..len {
+            let (bucket_idx, slot_idx) = locate(index);
+            let ptr = self.buckets[bucket_idx].load(Ordering::Acquire);
+            if ptr.is_null() {
+                continue;
+            }
+            let slot = unsafe { &(*ptr).slots[slot_idx] };
+            if slot.published.swap(false, Ordering::AcqRel) {
+                slot.value.with_mut(|ptr| unsafe { (*ptr).assume_init_drop() });
+            }
+        }
+        for bucket_idx in 0..NUM_BUCKETS {
+            let ptr = self.buckets[bucket_idx].swap(std::ptr::null_mut(), Ordering::AcqRel);
+            if !ptr.is_null() {
+                unsafe { drop(Box::from_raw(ptr)) };
+            }
+        }
+        self.clearing.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for Boxcar<T> {
+    fn drop(&mut self) {
+        let len = self.len();
+        for index in 0..len {
+            let (bucket_idx, slot_idx) = locate(index);
+            let ptr = self.buckets[bucket_idx].load(Ordering::Acquire);
+            if ptr.is_null() {
+                continue;
+            }
+            let slot = unsafe { &(*ptr).slots[slot_idx] };
+            if slot.published.load(Ordering::Acquire) {
+                slot.value.with_mut(|ptr| unsafe { (*ptr).assume_init_drop() });
+            }
+        }
+        for bucket_idx in 0..NUM_BUCKETS {
+            let ptr = self.buckets[bucket_idx].swap(std::ptr::null_mut(), Ordering::AcqRel);
+            if !ptr.is_null() {
+                unsafe { drop(Box::from_raw(ptr)) };
+            }
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for Boxcar<T> {}
+unsafe impl<T: Send + Sync> Sync for Boxcar<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extend_then_get_round_trips_every_index() {
+        let boxcar: Boxcar<usize> = Boxcar::new();
+        boxcar.extend((0..1000).collect());
+        for i in 0..1000 {
+            assert_eq!(boxcar.get(i), Some(&i));
+        }
+        assert_eq!(boxcar.get(1000), None);
+    }
+
+    #[test]
+    fn extend_across_multiple_buckets_preserves_order() {
+        let boxcar: Boxcar<usize> = Boxcar::new();
+        boxcar.extend(vec![0]);
+        boxcar.extend((1..5).collect());
+        boxcar.extend((5..100).collect());
+        assert_eq!(boxcar.len(), 100);
+        assert_eq!(boxcar.iter(0..100).copied().collect::<Vec<_>>(), (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn clear_drops_items_and_resets_len() {
+        let boxcar: Boxcar<usize> = Boxcar::new();
+        boxcar.extend((0..10).collect());
+        boxcar.clear();
+        assert!(boxcar.is_empty());
+        assert_eq!(boxcar.get(0), None);
+    }
+}
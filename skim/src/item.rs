@@ -2,14 +2,13 @@
 //! the internal states, such as selected or not
 use std::cmp::min;
 use std::default::Default;
-use std::ops::Deref;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
 
 use clap::ValueEnum;
 use clap::builder::PossibleValue;
 
-use crate::spinlock::{SpinLock, SpinLockGuard};
+use crate::boxcar::Boxcar;
+use crate::sync::{AtomicUsize, Ordering};
 use crate::{MatchRange, Rank, SkimItem};
 
 //------------------------------------------------------------------------------
@@ -101,26 +100,25 @@ impl Ord for MatchedItem {
 }
 
 //------------------------------------------------------------------------------
-const ITEM_POOL_CAPACITY: usize = 1024;
-
+/// An append-only pool of items, backed by [`Boxcar`] so the reader thread can keep
+/// publishing items while the matcher concurrently snapshots `num_not_taken()` of them, with
+/// no lock on the hot path.
 pub struct ItemPool {
-    length: AtomicUsize,
-    pool: SpinLock<Vec<Arc<dyn SkimItem>>>,
+    pool: Boxcar<Arc<dyn SkimItem>>,
     /// number of items that was `take`n
     taken: AtomicUsize,
 
     /// reverse first N lines as header
-    reserved_items: SpinLock<Vec<Arc<dyn SkimItem>>>,
+    reserved_items: Boxcar<Arc<dyn SkimItem>>,
     lines_to_reserve: usize,
 }
 
 impl Default for ItemPool {
     fn default() -> Self {
         Self {
-            length: AtomicUsize::new(0),
-            pool: SpinLock::new(Vec::with_capacity(ITEM_POOL_CAPACITY)),
+            pool: Boxcar::new(),
             taken: AtomicUsize::new(0),
-            reserved_items: SpinLock::new(Vec::new()),
+            reserved_items: Boxcar::new(),
             lines_to_reserve: 0,
         }
     }
@@ -128,13 +126,7 @@ impl Default for ItemPool {
 
 impl ItemPool {
     pub fn new() -> Self {
-        Self {
-            length: AtomicUsize::new(0),
-            pool: SpinLock::new(Vec::with_capacity(ITEM_POOL_CAPACITY)),
-            taken: AtomicUsize::new(0),
-            reserved_items: SpinLock::new(Vec::new()),
-            lines_to_reserve: 0,
-        }
+        Self::default()
     }
 
     pub fn lines_to_reserve(mut self, lines_to_reserve: usize) -> Self {
@@ -143,7 +135,7 @@ impl ItemPool {
     }
 
     pub fn len(&self) -> usize {
-        self.length.load(Ordering::SeqCst)
+        self.pool.committed_len()
     }
 
     pub fn is_empty(&self) -> bool {
@@ -151,7 +143,7 @@ impl ItemPool {
     }
 
     pub fn num_not_taken(&self) -> usize {
-        self.length.load(Ordering::SeqCst) - self.taken.load(Ordering::SeqCst)
+        self.pool.committed_len() - self.taken.load(Ordering::SeqCst)
     }
 
     pub fn num_taken(&self) -> usize {
@@ -159,62 +151,89 @@ impl ItemPool {
     }
 
     pub fn clear(&self) {
-        let mut items = self.pool.lock();
-        items.clear();
-        let mut header_items = self.reserved_items.lock();
-        header_items.clear();
+        self.pool.clear();
+        self.reserved_items.clear();
         self.taken.store(0, Ordering::SeqCst);
-        self.length.store(0, Ordering::SeqCst);
     }
 
     pub fn reset(&self) {
-        // lock to ensure consistency
-        let _items = self.pool.lock();
         self.taken.store(0, Ordering::SeqCst);
     }
 
-    /// append the items and return the new_size of the pool
-    pub fn append(&self, mut items: Vec<Arc<dyn SkimItem>>) -> usize {
+    /// append the items and return the new size of the pool
+    pub fn append(&self, items: Vec<Arc<dyn SkimItem>>) -> usize {
         let len = items.len();
         trace!("item pool, append {} items", len);
-        let mut pool = self.pool.lock();
-        let mut header_items = self.reserved_items.lock();
 
-        let to_reserve = self.lines_to_reserve - header_items.len();
+        let to_reserve = self.lines_to_reserve.saturating_sub(self.reserved_items.len());
         if to_reserve > 0 {
-            let to_reserve = min(to_reserve, items.len());
-            header_items.extend_from_slice(&items[..to_reserve]);
-            pool.extend_from_slice(&items[to_reserve..]);
+            let mut items = items;
+            let rest = items.split_off(min(to_reserve, items.len()));
+            self.reserved_items.extend(items);
+            self.pool.extend(rest);
         } else {
-            pool.append(&mut items);
+            self.pool.extend(items);
         }
-        self.length.store(pool.len(), Ordering::SeqCst);
         trace!("item pool, done append {} items", len);
-        pool.len()
+        self.pool.committed_len()
     }
 
-    pub fn take(&self) -> ItemPoolGuard<Arc<dyn SkimItem>> {
-        let guard = self.pool.lock();
-        let taken = self.taken.swap(guard.len(), Ordering::SeqCst);
-        ItemPoolGuard { guard, start: taken }
+    pub fn take(&self) -> ItemPoolGuard<'_, Arc<dyn SkimItem>> {
+        let end = self.pool.committed_len();
+        let start = self.taken.swap(end, Ordering::SeqCst);
+        ItemPoolGuard {
+            _reader: self.pool.enter(),
+            pool: &self.pool,
+            start,
+            end,
+        }
     }
 
-    pub fn reserved(&self) -> ItemPoolGuard<Arc<dyn SkimItem>> {
-        let guard = self.reserved_items.lock();
-        ItemPoolGuard { guard, start: 0 }
+    pub fn reserved(&self) -> ItemPoolGuard<'_, Arc<dyn SkimItem>> {
+        ItemPoolGuard {
+            _reader: self.reserved_items.enter(),
+            pool: &self.reserved_items,
+            start: 0,
+            end: self.reserved_items.committed_len(),
+        }
     }
 }
 
+/// A read-only, lock-free snapshot of the items appended to an [`ItemPool`] since some point
+/// (e.g. since the last `take()`). Since a [`Boxcar`] never stores its elements contiguously,
+/// this yields an iterator rather than a slice. `end` is captured once, at guard creation, so a
+/// concurrent `append()` racing with the matcher reading this guard never grows or shrinks it.
+/// Holding `_reader` for the guard's lifetime registers it with the backing [`Boxcar`], so a
+/// concurrent `clear()` blocks until every outstanding guard has dropped instead of
+/// freeing buckets the guard is still iterating.
 pub struct ItemPoolGuard<'a, T: Sized + 'a> {
-    guard: SpinLockGuard<'a, Vec<T>>,
+    pool: &'a Boxcar<T>,
+    /// kept alive only so `clear()` knows not to free our buckets; never read directly
+    _reader: crate::boxcar::ReadGuard<'a, T>,
     start: usize,
+    end: usize,
+}
+
+impl<T: Sized> ItemPoolGuard<'_, T> {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.pool.iter(self.start..self.end)
+    }
 }
 
-impl<T: Sized> Deref for ItemPoolGuard<'_, T> {
-    type Target = [T];
+impl<'a, T: Sized> IntoIterator for &'a ItemPoolGuard<'a, T> {
+    type Item = &'a T;
+    type IntoIter = Box<dyn Iterator<Item = &'a T> + 'a>;
 
-    fn deref(&self) -> &[T] {
-        &self.guard[self.start..]
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
     }
 }
 
@@ -0,0 +1,56 @@
+//! Configuration consumed by [`crate::reader::Reader::with_options`].
+//!
+//! Kept to the handful of fields the reader/matcher pipeline in this tree actually reads; the
+//! rest of skim's CLI surface lives alongside it in the full crate.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::reader::CommandCollector;
+
+pub struct SkimOptions {
+    pub(crate) cmd_collector: Rc<RefCell<dyn CommandCollector>>,
+    /// capacity of the bounded reader/collector ring buffer, set via `--read-buffer N` or
+    /// [`SkimOptionsBuilder::read_buffer`]; `None` (the default) keeps today's unbounded
+    /// `SpinLock<Vec<_>>` handoff.
+    pub(crate) read_buffer: Option<usize>,
+}
+
+impl SkimOptions {
+    pub fn builder() -> SkimOptionsBuilder {
+        SkimOptionsBuilder::default()
+    }
+}
+
+#[derive(Default)]
+pub struct SkimOptionsBuilder {
+    cmd_collector: Option<Rc<RefCell<dyn CommandCollector>>>,
+    read_buffer: Option<usize>,
+}
+
+impl SkimOptionsBuilder {
+    pub fn cmd_collector(mut self, cmd_collector: Rc<RefCell<dyn CommandCollector>>) -> Self {
+        self.cmd_collector = Some(cmd_collector);
+        self
+    }
+
+    /// cap the reader/collector handoff at `capacity` items, giving the collector backpressure
+    /// instead of growing without bound when the matcher can't keep up (`--read-buffer N`).
+    ///
+    /// # Panics
+    /// Panics if `capacity` is `0`, here rather than later inside [`crate::ring::RingBuffer`],
+    /// so a bad `--read-buffer 0` is reported against the option that caused it instead of
+    /// surfacing deep in `Reader::run`.
+    pub fn read_buffer(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "read_buffer capacity must be greater than 0, got 0");
+        self.read_buffer = Some(capacity);
+        self
+    }
+
+    pub fn build(self) -> SkimOptions {
+        SkimOptions {
+            cmd_collector: self.cmd_collector.expect("cmd_collector is required"),
+            read_buffer: self.read_buffer,
+        }
+    }
+}
@@ -3,63 +3,172 @@
 //! After reading in a line, reader will save an item into the pool(items)
 use crate::global::mark_new_run;
 use crate::options::SkimOptions;
+use crate::ring::RingBuffer;
 use crate::spinlock::SpinLock;
+use crate::sync::{AtomicBool, AtomicUsize, Ordering, thread};
+use crate::waker::ThreadWaker;
 use crate::{SkimItem, SkimItemReceiver};
 use crossbeam::channel::{Sender, bounded, select};
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::thread;
 
 const CHANNEL_SIZE: usize = 1024;
 
+/// the reader/collector side handoff: unbounded by default (today's behavior), or a bounded
+/// ring buffer when `SkimOptions::read_buffer` is set, giving the collector backpressure when
+/// the matcher can't keep up.
+#[derive(Clone)]
+enum ItemBuffer {
+    Unbounded(Arc<SpinLock<Vec<Arc<dyn SkimItem>>>>),
+    Bounded(Arc<RingBuffer<Arc<dyn SkimItem>>>),
+}
+
+impl ItemBuffer {
+    fn new(read_buffer: Option<usize>) -> Self {
+        match read_buffer {
+            Some(capacity) => ItemBuffer::Bounded(Arc::new(RingBuffer::new(capacity))),
+            None => ItemBuffer::Unbounded(Arc::new(SpinLock::new(Vec::new()))),
+        }
+    }
+
+    /// push a single item, blocking the collector thread while a bounded buffer is full.
+    /// Returns `false` if a bounded buffer was [`interrupt`](Self::interrupt)ed while the
+    /// collector was parked on it, so the caller can stop collecting instead of leaving the
+    /// collector thread parked forever behind a consumer that stopped draining.
+    fn push(&self, item: Arc<dyn SkimItem>) -> bool {
+        match self {
+            ItemBuffer::Unbounded(items) => {
+                items.lock().push(item);
+                true
+            }
+            ItemBuffer::Bounded(ring) => ring.push(item),
+        }
+    }
+
+    /// wake a collector thread parked in [`push`](Self::push) on a full bounded buffer so it can
+    /// observe shutdown; a no-op for the unbounded buffer, which never blocks on push
+    fn interrupt(&self) {
+        if let ItemBuffer::Bounded(ring) = self {
+            ring.interrupt();
+        }
+    }
+
+    fn take(&self) -> Vec<Arc<dyn SkimItem>> {
+        match self {
+            ItemBuffer::Unbounded(items) => {
+                let mut items = items.lock();
+                let mut ret = Vec::with_capacity(items.len());
+                ret.append(&mut items);
+                ret
+            }
+            ItemBuffer::Bounded(ring) => ring.drain(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            ItemBuffer::Unbounded(items) => items.lock().is_empty(),
+            ItemBuffer::Bounded(ring) => ring.is_empty(),
+        }
+    }
+}
+
+/// Shared handle for reporting component thread start/stop, given to [`CommandCollector::invoke`]
+/// instead of a bare `AtomicUsize`. Bundling the counter with the [`ThreadWaker`] means every
+/// decrementer -- not just `collect_item`'s own thread -- wakes anyone parked in
+/// `ReaderControl::kill`, so the last component to stop can never leave `kill()` parked forever.
+pub struct ComponentCounter {
+    count: AtomicUsize,
+    waker: Arc<ThreadWaker>,
+}
+
+impl ComponentCounter {
+    fn new(waker: Arc<ThreadWaker>) -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+            waker,
+        }
+    }
+
+    /// a component thread has started
+    pub fn increment(&self) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// a component thread has terminated; wakes anyone parked waiting for the count to reach zero
+    pub fn decrement(&self) {
+        self.count.fetch_sub(1, Ordering::SeqCst);
+        self.waker.wake();
+    }
+
+    pub fn load(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+}
+
 pub trait CommandCollector {
     /// execute the `cmd` and produce a
     /// - skim item producer
     /// - a channel sender, any message send would mean to terminate the `cmd` process (for now).
     ///
     /// Internally, the command collector may start several threads(components), the collector
-    /// should add `1` on every thread creation and sub `1` on thread termination. reader would use
-    /// this information to determine whether the collector had stopped or not.
-    fn invoke(&mut self, cmd: &str, components_to_stop: Arc<AtomicUsize>) -> (SkimItemReceiver, Sender<i32>);
+    /// should call `increment()` on every thread creation and `decrement()` on thread termination.
+    /// reader would use this information to determine whether the collector had stopped or not.
+    fn invoke(&mut self, cmd: &str, components_to_stop: Arc<ComponentCounter>) -> (SkimItemReceiver, Sender<i32>);
+}
+
+/// Something `ReaderControl::kill()` can forward its interrupt signal into. The thread-backed
+/// `CommandCollector`/`collect_item` path is happy with a plain channel `send`, but an
+/// externally supplied source (e.g. [`crate::async_collector::bridge`]) may need to wake an
+/// async task instead of a parked thread, so `Reader::source` takes this trait rather than a
+/// concrete `Sender<i32>`.
+pub trait SourceInterrupt: Send {
+    fn interrupt(&self);
+}
+
+impl SourceInterrupt for Sender<i32> {
+    fn interrupt(&self) {
+        let _ = self.send(1);
+    }
 }
 
 pub struct ReaderControl {
     tx_interrupt: Sender<i32>,
-    tx_interrupt_cmd: Option<Sender<i32>>,
-    components_to_stop: Arc<AtomicUsize>,
-    items: Arc<SpinLock<Vec<Arc<dyn SkimItem>>>>,
+    tx_interrupt_cmd: Option<Box<dyn SourceInterrupt>>,
+    components_to_stop: Arc<ComponentCounter>,
+    stop_waker: Arc<ThreadWaker>,
+    items: ItemBuffer,
 }
 
 impl ReaderControl {
     pub fn kill(self) {
-        debug!(
-            "kill reader, components before: {}",
-            self.components_to_stop.load(Ordering::SeqCst)
-        );
+        debug!("kill reader, components before: {}", self.components_to_stop.load());
 
-        let _ = self.tx_interrupt_cmd.map(|tx| tx.send(1));
+        if let Some(tx) = self.tx_interrupt_cmd {
+            tx.interrupt();
+        }
         let _ = self.tx_interrupt.send(1);
-        while self.components_to_stop.load(Ordering::SeqCst) != 0 {}
+        // also wake a collector thread that might be parked inside `items.push` on a full
+        // bounded buffer, otherwise it never reaches the `select!` that watches `tx_interrupt`
+        self.items.interrupt();
+        self.stop_waker.park_until(|| self.components_to_stop.load() == 0);
     }
 
     pub fn take(&self) -> Vec<Arc<dyn SkimItem>> {
-        let mut items = self.items.lock();
-        let mut ret = Vec::with_capacity(items.len());
-        ret.append(&mut items);
-        ret
+        self.items.take()
     }
 
     pub fn is_done(&self) -> bool {
-        let items = self.items.lock();
-        self.components_to_stop.load(Ordering::SeqCst) == 0 && items.is_empty()
+        self.components_to_stop.load() == 0 && self.items.is_empty()
     }
 }
 
 pub struct Reader {
     cmd_collector: Rc<RefCell<dyn CommandCollector>>,
     rx_item: Option<SkimItemReceiver>,
+    tx_interrupt_source: Option<Box<dyn SourceInterrupt>>,
+    read_buffer: Option<usize>,
 }
 
 impl Reader {
@@ -67,26 +176,47 @@ impl Reader {
         Self {
             cmd_collector: options.cmd_collector.clone(),
             rx_item: None,
+            tx_interrupt_source: None,
+            read_buffer: options.read_buffer,
         }
     }
 
-    pub fn source(mut self, rx_item: Option<SkimItemReceiver>) -> Self {
-        self.rx_item = rx_item;
+    /// supply an externally produced item stream in place of spawning `cmd` through the
+    /// `CommandCollector`, e.g. the `(rx_item, tx_interrupt, drive)` returned by
+    /// [`crate::async_collector::bridge`]. `tx_interrupt`, if given, has [`SourceInterrupt::interrupt`]
+    /// called on it by `ReaderControl::kill()` exactly like the `CommandCollector` path's own
+    /// interrupt sender, so `kill()` alone is enough to stop an externally supplied source.
+    pub fn source(mut self, source: Option<(SkimItemReceiver, Box<dyn SourceInterrupt>)>) -> Self {
+        match source {
+            Some((rx_item, tx_interrupt)) => {
+                self.rx_item = Some(rx_item);
+                self.tx_interrupt_source = Some(tx_interrupt);
+            }
+            None => {
+                self.rx_item = None;
+                self.tx_interrupt_source = None;
+            }
+        }
         self
     }
 
     pub fn run(&mut self, cmd: &str) -> ReaderControl {
         mark_new_run(cmd);
 
-        let components_to_stop: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
-        let items = Arc::new(SpinLock::new(Vec::new()));
+        let stop_waker = Arc::new(ThreadWaker::new());
+        let components_to_stop = Arc::new(ComponentCounter::new(stop_waker.clone()));
+        let items = ItemBuffer::new(self.read_buffer);
         let items_clone = items.clone();
 
-        let (rx_item, tx_interrupt_cmd) = self.rx_item.take().map(|rx| (rx, None)).unwrap_or_else(|| {
-            let components_to_stop_clone = components_to_stop.clone();
-            let (rx_item, tx_interrupt_cmd) = self.cmd_collector.borrow_mut().invoke(cmd, components_to_stop_clone);
-            (rx_item, Some(tx_interrupt_cmd))
-        });
+        let (rx_item, tx_interrupt_cmd) = self
+            .rx_item
+            .take()
+            .map(|rx| (rx, self.tx_interrupt_source.take()))
+            .unwrap_or_else(|| {
+                let components_to_stop_clone = components_to_stop.clone();
+                let (rx_item, tx_interrupt_cmd) = self.cmd_collector.borrow_mut().invoke(cmd, components_to_stop_clone);
+                (rx_item, Some(Box::new(tx_interrupt_cmd) as Box<dyn SourceInterrupt>))
+            });
 
         let components_to_stop_clone = components_to_stop.clone();
         let tx_interrupt = collect_item(components_to_stop_clone, rx_item, items_clone);
@@ -95,45 +225,47 @@ impl Reader {
             tx_interrupt,
             tx_interrupt_cmd,
             components_to_stop,
+            stop_waker,
             items,
         }
     }
 }
 
 fn collect_item(
-    components_to_stop: Arc<AtomicUsize>,
+    components_to_stop: Arc<ComponentCounter>,
     rx_item: SkimItemReceiver,
-    items: Arc<SpinLock<Vec<Arc<dyn SkimItem>>>>,
+    items: ItemBuffer,
 ) -> Sender<i32> {
     let (tx_interrupt, rx_interrupt) = bounded(CHANNEL_SIZE);
 
     let started = Arc::new(AtomicBool::new(false));
     let started_clone = started.clone();
+    let start_waker = Arc::new(ThreadWaker::new());
+    let start_waker_clone = start_waker.clone();
     thread::spawn(move || {
         debug!("reader: collect_item start");
-        components_to_stop.fetch_add(1, Ordering::SeqCst);
+        components_to_stop.increment();
         started_clone.store(true, Ordering::SeqCst); // notify parent that it is started
+        start_waker_clone.wake();
 
         loop {
             select! {
                 recv(rx_item) -> new_item => match new_item {
-                    Ok(item) => {
-                        let mut vec = items.lock();
-                        vec.push(item);
-                    }
+                    // a full bounded buffer interrupted while we were parked in `push` means
+                    // shutdown is already underway, so stop collecting just like the
+                    // `rx_interrupt` arm below
+                    Ok(item) => if !items.push(item) { break },
                     Err(_) => break,
                 },
                 recv(rx_interrupt) -> _msg => break,
             }
         }
 
-        components_to_stop.fetch_sub(1, Ordering::SeqCst);
+        components_to_stop.decrement();
         debug!("reader: collect_item stop");
     });
 
-    while !started.load(Ordering::SeqCst) {
-        // busy waiting for the thread to start. (components_to_stop is added)
-    }
+    start_waker.park_until(|| started.load(Ordering::SeqCst));
 
     tx_interrupt
 }
@@ -0,0 +1,170 @@
+//! A fixed-capacity, single-producer/single-consumer ring buffer used to give the reader a
+//! memory ceiling when the input source outpaces the matcher.
+//!
+//! Modeled on smol's `pipe`: a circular buffer of slots with atomic head/tail counters. The
+//! writer blocks (via [`ThreadWaker`], not a busy spin) when the buffer is full; the reader
+//! wakes it back up as soon as it drains anything.
+
+use std::mem::MaybeUninit;
+
+use crate::sync::{AtomicBool, AtomicUsize, Ordering, UnsafeCell};
+use crate::waker::ThreadWaker;
+
+pub struct RingBuffer<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    capacity: usize,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    /// woken by the consumer whenever it frees up space, so a blocked `push` can retry
+    space_waker: ThreadWaker,
+    /// set by [`RingBuffer::interrupt`] so a `push` parked on a full buffer can give up instead
+    /// of blocking shutdown forever when the consumer has stopped draining
+    interrupted: AtomicBool,
+}
+
+unsafe impl<T: Send> Send for RingBuffer<T> {}
+unsafe impl<T: Send> Sync for RingBuffer<T> {}
+
+impl<T> RingBuffer<T> {
+    /// # Panics
+    /// Panics if `capacity` is `0`. Callers going through
+    /// [`crate::options::SkimOptionsBuilder::read_buffer`] get this rejected earlier, against
+    /// the option that caused it; this assert is just the last line of defense for anyone
+    /// constructing a `RingBuffer` directly.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "RingBuffer capacity must be positive");
+        Self {
+            slots: (0..capacity).map(|_| UnsafeCell::new(MaybeUninit::uninit())).collect(),
+            capacity,
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            space_waker: ThreadWaker::new(),
+            interrupted: AtomicBool::new(false),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        // read `head` before `tail`: `head` only ever advances up to `tail`, so a `tail` read
+        // that comes after can only be >= the `head` we already have, never underflowing. The
+        // reverse order would let a concurrent `drain()` advance `head` past a stale `tail`.
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        tail - head
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// push a single item, parking the caller while the buffer is full instead of spinning.
+    /// Returns `false` without pushing if [`RingBuffer::interrupt`] fires while parked, so a
+    /// shutdown isn't blocked behind a consumer that has stopped draining.
+    pub fn push(&self, item: T) -> bool {
+        loop {
+            let tail = self.tail.load(Ordering::Acquire);
+            let head = self.head.load(Ordering::Acquire);
+            if tail - head < self.capacity {
+                let slot = tail % self.capacity;
+                self.slots[slot].with_mut(|ptr| unsafe { (*ptr).write(item) });
+                self.tail.store(tail + 1, Ordering::Release);
+                return true;
+            }
+            if self.interrupted.load(Ordering::SeqCst) {
+                return false;
+            }
+            self.space_waker
+                .park_until(|| self.head.load(Ordering::Acquire) != head || self.interrupted.load(Ordering::SeqCst));
+        }
+    }
+
+    /// wake any writer parked on a full buffer so it can observe shutdown and give up instead of
+    /// blocking forever behind a consumer that stopped draining
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::SeqCst);
+        self.space_waker.wake();
+    }
+
+    /// drain every item currently available without blocking, waking a writer parked on space
+    pub fn drain(&self) -> Vec<T> {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        let mut out = Vec::with_capacity(tail - head);
+        for i in head..tail {
+            let slot = i % self.capacity;
+            out.push(self.slots[slot].with_mut(|ptr| unsafe { (*ptr).assume_init_read() }));
+        }
+        self.head.store(tail, Ordering::Release);
+        if !out.is_empty() {
+            self.space_waker.wake();
+        }
+        out
+    }
+}
+
+impl<T> Drop for RingBuffer<T> {
+    fn drop(&mut self) {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        for i in head..tail {
+            let slot = i % self.capacity;
+            self.slots[slot].with_mut(|ptr| unsafe { (*ptr).assume_init_drop() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_then_drain_preserves_order() {
+        let ring: RingBuffer<usize> = RingBuffer::new(4);
+        ring.push(1);
+        ring.push(2);
+        ring.push(3);
+        assert_eq!(ring.len(), 3);
+        assert_eq!(ring.drain(), vec![1, 2, 3]);
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn push_wraps_around_the_backing_slots() {
+        let ring: RingBuffer<usize> = RingBuffer::new(2);
+        ring.push(1);
+        ring.push(2);
+        assert_eq!(ring.drain(), vec![1, 2]);
+        // head/tail have both advanced past the backing array's length now, exercising the
+        // `% capacity` wrap on the next round of pushes
+        ring.push(3);
+        ring.push(4);
+        assert_eq!(ring.drain(), vec![3, 4]);
+    }
+
+    #[test]
+    fn drain_on_empty_buffer_returns_nothing() {
+        let ring: RingBuffer<usize> = RingBuffer::new(4);
+        assert_eq!(ring.drain(), Vec::<usize>::new());
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn interrupt_frees_a_push_blocked_on_a_full_buffer() {
+        use std::sync::Arc;
+        use std::thread;
+        use std::time::Duration;
+
+        let ring: Arc<RingBuffer<usize>> = Arc::new(RingBuffer::new(1));
+        ring.push(1);
+
+        let blocked = {
+            let ring = ring.clone();
+            thread::spawn(move || ring.push(2))
+        };
+
+        // give the pusher a chance to park on the full buffer before we interrupt it
+        thread::sleep(Duration::from_millis(50));
+        ring.interrupt();
+
+        assert!(!blocked.join().unwrap());
+    }
+}
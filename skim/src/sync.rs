@@ -0,0 +1,61 @@
+//! Swappable atomics/interior-mutability layer for skim's concurrency primitives
+//! (`spinlock`, [`crate::boxcar::Boxcar`], [`crate::ring::RingBuffer`], the reader's
+//! start/stop handshake).
+//!
+//! Under `#[cfg(loom)]` these come from `loom` instead of `std`, so `tests/loom.rs` can
+//! model-check the orderings with `RUSTFLAGS="--cfg loom" LOOM_MAX_PREEMPTIONS=2 cargo test
+//! --test loom --release`. Everything else in the crate should reach atomics and `UnsafeCell`
+//! through this module instead of `std` directly, so a single `#[cfg(loom)]` switch covers the
+//! whole concurrent core.
+//!
+//! `UnsafeCell` is wrapped rather than re-exported because loom's version tracks accesses
+//! through `with`/`with_mut` closures instead of a raw `get()` pointer, so callers use that
+//! same closure-based API regardless of backend.
+
+#[cfg(not(loom))]
+mod backend {
+    pub use std::sync::{Condvar, Mutex};
+    pub use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+    pub use std::thread;
+
+    pub struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+    impl<T> UnsafeCell<T> {
+        pub fn new(data: T) -> Self {
+            Self(std::cell::UnsafeCell::new(data))
+        }
+
+        pub fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+            f(self.0.get())
+        }
+
+        pub fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+            f(self.0.get())
+        }
+    }
+}
+
+#[cfg(loom)]
+mod backend {
+    pub use loom::sync::{Condvar, Mutex};
+    pub use loom::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+    pub use loom::thread;
+
+    pub struct UnsafeCell<T>(loom::cell::UnsafeCell<T>);
+
+    impl<T> UnsafeCell<T> {
+        pub fn new(data: T) -> Self {
+            Self(loom::cell::UnsafeCell::new(data))
+        }
+
+        pub fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+            self.0.with(f)
+        }
+
+        pub fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+            self.0.with_mut(f)
+        }
+    }
+}
+
+pub use backend::*;
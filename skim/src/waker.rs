@@ -0,0 +1,87 @@
+//! A single-slot wakeup primitive used in place of busy-wait spin loops.
+//!
+//! Built on a `Mutex`/`Condvar` pair rather than raw `std::thread::park`/`unpark`: loom models
+//! blocking only through its own `Mutex`/`Condvar` (its `thread` module has no `current()` or
+//! `Thread::unpark()` to model-check against), so routing through [`crate::sync`] here is what
+//! lets `tests/loom.rs` actually explore this wakeup path instead of failing to compile under
+//! `#[cfg(loom)]`. The `Mutex` critical section is fine on the non-loom backend too, since it
+//! only guards coordination events (start/stop), not the per-item hot path.
+
+use crate::sync::{Condvar, Mutex};
+
+pub struct ThreadWaker {
+    lock: Mutex<()>,
+    condvar: Condvar,
+}
+
+impl ThreadWaker {
+    pub fn new() -> Self {
+        Self {
+            lock: Mutex::new(()),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// block until `condition` holds, as a standard condvar predicate loop: take the lock,
+    /// recheck `condition` each time we're woken, and keep waiting while it's false (covers
+    /// both the "already true" and spurious-wakeup cases).
+    pub fn park_until(&self, mut condition: impl FnMut() -> bool) {
+        let mut guard = self.lock.lock().unwrap();
+        while !condition() {
+            guard = self.condvar.wait(guard).unwrap();
+        }
+    }
+
+    /// wake whoever is currently parked in [`Self::park_until`], if anyone
+    pub fn wake(&self) {
+        let _guard = self.lock.lock().unwrap();
+        self.condvar.notify_all();
+    }
+}
+
+impl Default for ThreadWaker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn park_until_returns_immediately_if_condition_already_holds() {
+        let waker = ThreadWaker::new();
+        waker.park_until(|| true);
+    }
+
+    #[test]
+    fn wake_unparks_a_waiting_thread() {
+        let waker = Arc::new(ThreadWaker::new());
+        let condition = Arc::new(AtomicBool::new(false));
+
+        let waiter = {
+            let waker = waker.clone();
+            let condition = condition.clone();
+            thread::spawn(move || waker.park_until(|| condition.load(Ordering::SeqCst)))
+        };
+
+        // give the waiter a chance to register itself before we flip the condition and wake it
+        thread::sleep(Duration::from_millis(50));
+        condition.store(true, Ordering::SeqCst);
+        waker.wake();
+
+        waiter.join().unwrap();
+    }
+
+    #[test]
+    fn wake_with_no_waiter_is_a_no_op() {
+        let waker = ThreadWaker::new();
+        waker.wake();
+    }
+}